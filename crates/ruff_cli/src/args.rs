@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+
+/// Arguments for the `ruff graph` subcommand, alongside the existing `Command::Lsp`.
+#[derive(Debug, Args)]
+pub(crate) struct GraphArguments {
+    /// List of files or directories to export a symbol reference graph for.
+    pub(crate) files: Vec<PathBuf>,
+    /// The format to export the graph in.
+    #[arg(long, value_enum, default_value_t = GraphFormat::Json)]
+    pub(crate) format: GraphFormat,
+    /// Ignore all configuration files.
+    #[arg(long)]
+    pub(crate) isolated: bool,
+    /// Path to the `pyproject.toml` or `ruff.toml` file to use for configuration.
+    #[arg(long)]
+    pub(crate) config: Option<PathBuf>,
+    /// The name of the file when passing it through stdin.
+    #[arg(long)]
+    pub(crate) stdin_filename: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub(crate) enum GraphFormat {
+    /// The graph's nodes and edges, as a single JSON object.
+    #[default]
+    Json,
+    /// A flat `scope -> symbol` edge list, one per line.
+    EdgeList,
+    /// A minimal Cypher `CREATE` script, for loading the graph into a graph database.
+    Cypher,
+}
@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use rayon::iter::Either::{Left, Right};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use ruff_graph::SymbolGraph;
+use ruff_workspace::resolver::python_files_in_path;
+
+use crate::args::{GraphArguments, GraphFormat, Overrides};
+use crate::resolve::resolve;
+use crate::ExitStatus;
+
+/// Export a cross-file symbol reference graph for the files in `arguments`, and return the
+/// exit status.
+pub(crate) fn graph(arguments: GraphArguments, overrides: &Overrides) -> Result<ExitStatus> {
+    let pyproject_config = resolve(
+        arguments.isolated,
+        arguments.config.as_deref(),
+        overrides,
+        arguments.stdin_filename.as_deref(),
+    )?;
+    let (paths, resolver) = python_files_in_path(&arguments.files, &pyproject_config, overrides)?;
+
+    let (paths, errors): (Vec<PathBuf>, Vec<_>) = paths
+        .into_par_iter()
+        .partition_map(|entry| match entry {
+            Ok(entry) => Left(entry.into_path()),
+            Err(err) => Right(err),
+        });
+    for err in errors {
+        eprintln!("{err}");
+    }
+
+    let settings = resolver.fallback_settings();
+    let graph = SymbolGraph::build(&paths, &settings.linter)?;
+
+    match arguments.format {
+        GraphFormat::Json => println!("{}", serde_json::to_string_pretty(&graph)?),
+        GraphFormat::EdgeList => println!("{}", graph.to_edge_list()),
+        GraphFormat::Cypher => println!("{}", graph.to_cypher()),
+    }
+
+    Ok(ExitStatus::Success)
+}
@@ -0,0 +1,228 @@
+//! Builds a cross-file symbol reference graph from Ruff's semantic analysis.
+//!
+//! Nodes are bindings (imports, function/class defs, ...), identified by their module, the
+//! name they're bound under, and the scope they live in. Edges run from each resolved reference back to the binding it
+//! resolved to, annotated with the context (runtime vs. typing) the reference occurred in.
+//! This is the same resolution data the linter already computes for rules like
+//! `flake8-type-checking`; this crate just retains it as a standalone artifact instead of
+//! discarding it once diagnostics have been collected.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use ruff::linter::check_with_semantic_model;
+use ruff::settings::{flags::Noqa, Settings};
+use ruff_python_ast::{PySourceType, Ranged};
+use ruff_python_semantic::context::ExecutionContext;
+use ruff_python_semantic::{Binding, ScopeKind, SemanticModel};
+
+/// A binding, identified by its module, the name it's bound under, and the scope it's
+/// defined in.
+#[derive(Debug, Clone, Serialize)]
+pub struct Node {
+    pub id: usize,
+    pub module: PathBuf,
+    /// The name the binding is bound under in its module (`bar` for `from foo import bar`),
+    /// not a dotted path.
+    pub qualified_name: String,
+    pub scope: String,
+}
+
+/// A resolved reference to a [`Node`], annotated with the context it occurred in. There's no
+/// node for the reference's own site -- a "who reads this symbol" query only needs the scope
+/// the read happened in, not a full binding.
+#[derive(Debug, Clone, Serialize)]
+pub struct Edge {
+    pub to: usize,
+    pub from_scope: String,
+    pub context: Context,
+    pub in_type_checking_block: bool,
+    pub in_typing_only_annotation: bool,
+}
+
+/// Mirrors [`ExecutionContext`], made serializable for the graph's on-disk formats.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Context {
+    Runtime,
+    Typing,
+}
+
+impl From<ExecutionContext> for Context {
+    fn from(context: ExecutionContext) -> Self {
+        match context {
+            ExecutionContext::Runtime => Self::Runtime,
+            ExecutionContext::Typing => Self::Typing,
+        }
+    }
+}
+
+/// The full symbol reference graph for a set of modules.
+#[derive(Debug, Default, Serialize)]
+pub struct SymbolGraph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+/// What a module contributes to cross-file resolution: the names it binds at module scope
+/// (candidates for other modules' imports to resolve to), and its own import bindings (which
+/// need resolving against some other module's exports).
+struct ModuleInfo {
+    module_name: String,
+    exports: HashMap<String, usize>,
+    /// `(qualified name of the imported symbol, scope the import binding lives in, the import
+    /// binding's execution context)`.
+    imports: Vec<(String, String, ExecutionContext)>,
+}
+
+impl SymbolGraph {
+    /// Walks `paths`, builds each module's semantic model, and resolves it into nodes and
+    /// edges. Files that fail to parse are skipped; the graph only reflects modules Ruff
+    /// could successfully analyze. Once every module has been analyzed, a second pass links
+    /// import bindings to the node of the module that actually defines the imported symbol,
+    /// so the graph spans files instead of being one independent graph per file.
+    pub fn build(paths: &[PathBuf], settings: &Settings) -> Result<Self> {
+        let mut graph = Self::default();
+        let mut modules = Vec::with_capacity(paths.len());
+        for path in paths {
+            modules.push(graph.analyze_module(path, settings)?);
+        }
+        graph.link_imports(&modules);
+        Ok(graph)
+    }
+
+    /// Adds `path`'s bindings and their in-file references as nodes and edges, and returns
+    /// the module-scope exports and import bindings needed to resolve imports across files.
+    fn analyze_module(&mut self, path: &Path, settings: &Settings) -> Result<ModuleInfo> {
+        let source = std::fs::read_to_string(path)?;
+        let source_type = PySourceType::from(path);
+        let result = check_with_semantic_model(&source, path, settings, Noqa::Enabled, source_type);
+        let semantic = &result.semantic;
+
+        let mut exports = HashMap::new();
+        let mut imports = Vec::new();
+
+        let base_id = self.nodes.len();
+        for (index, binding) in semantic.all_bindings().map(|(_, binding)| binding).enumerate() {
+            let id = base_id + index;
+            let name = binding.name(&result.locator).to_string();
+
+            if let Some(import) = binding.kind.as_any_import() {
+                imports.push((
+                    import.qualified_name().to_string(),
+                    format!("{:?}", binding.scope_id()),
+                    binding.context,
+                ));
+            } else if is_module_scope(semantic, binding) {
+                exports.insert(name.clone(), id);
+            }
+
+            self.nodes.push(Node {
+                id,
+                module: path.to_path_buf(),
+                qualified_name: name,
+                scope: format!("{:?}", binding.scope_id()),
+            });
+
+            for reference_id in binding.references() {
+                let reference = semantic.reference(reference_id);
+                self.edges.push(Edge {
+                    to: id,
+                    from_scope: format!("{:?}", reference.scope_id()),
+                    context: reference.context().into(),
+                    in_type_checking_block: reference.in_type_checking_block(),
+                    in_typing_only_annotation: reference.in_typing_only_annotation(),
+                });
+            }
+        }
+
+        Ok(ModuleInfo {
+            module_name: module_name_for(path),
+            exports,
+            imports,
+        })
+    }
+
+    /// For every import binding collected in [`Self::analyze_module`], resolves the imported
+    /// module and symbol name against the other modules' exports, and adds a cross-file edge
+    /// from the importing scope to the defining binding. Such an edge carries the import
+    /// binding's execution context; its `in_type_checking_block` and
+    /// `in_typing_only_annotation` flags are always `false`. Imports that don't resolve to one of
+    /// `modules` -- third-party packages, the standard library, symbols re-exported through a
+    /// layer we didn't index -- are left alone rather than guessed at.
+    fn link_imports(&mut self, modules: &[ModuleInfo]) {
+        let by_module_name: HashMap<&str, &ModuleInfo> = modules
+            .iter()
+            .map(|module| (module.module_name.as_str(), module))
+            .collect();
+
+        for module in modules {
+            for (qualified_name, scope, context) in &module.imports {
+                let Some((module_name, member_name)) = qualified_name.rsplit_once('.') else {
+                    continue;
+                };
+                let Some(target) = by_module_name.get(module_name) else {
+                    continue;
+                };
+                let Some(&export_id) = target.exports.get(member_name) else {
+                    continue;
+                };
+
+                self.edges.push(Edge {
+                    to: export_id,
+                    from_scope: scope.clone(),
+                    context: (*context).into(),
+                    in_type_checking_block: false,
+                    in_typing_only_annotation: false,
+                });
+            }
+        }
+    }
+
+    /// A flat `scope -> symbol` edge list, one per line, for tools that don't want to parse JSON.
+    pub fn to_edge_list(&self) -> String {
+        self.edges
+            .iter()
+            .map(|edge| format!("{} -> {}", edge.from_scope, edge.to))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// A minimal Cypher `CREATE` script, for loading the graph into a graph database.
+    pub fn to_cypher(&self) -> String {
+        let mut script = String::new();
+        for node in &self.nodes {
+            script.push_str(&format!(
+                "CREATE (:Symbol {{id: {}, name: {:?}, scope: {:?}}})\n",
+                node.id, node.qualified_name, node.scope
+            ));
+        }
+        for edge in &self.edges {
+            script.push_str(&format!(
+                "MATCH (b:Symbol {{id: {}}}) CREATE (:Scope {{name: {:?}}})-[:REFERENCES {{context: {:?}}}]->(b)\n",
+                edge.to, edge.from_scope, edge.context
+            ));
+        }
+        script
+    }
+}
+
+/// Whether `binding` lives directly in `semantic`'s module scope, i.e. it's a plausible
+/// target for another module's `import`/`from ... import`.
+fn is_module_scope(semantic: &SemanticModel, binding: &Binding) -> bool {
+    matches!(semantic.scope(binding.scope_id()).kind, ScopeKind::Module)
+}
+
+/// A best-effort dotted module name for `path`, used to match it against the module name an
+/// import resolves to. Only the file stem is used -- there's no project root available here to
+/// resolve a proper package-qualified name against, so this only links imports that reference a
+/// module by its bare name (`from helpers import foo`), not a fully package-qualified one
+/// (`from myproject.utils.helpers import foo`).
+fn module_name_for(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
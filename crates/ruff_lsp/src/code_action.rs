@@ -0,0 +1,283 @@
+use std::path::Path;
+
+use ruff::linter::lint_fix;
+use ruff::settings::{flags::Noqa, Settings};
+use ruff_diagnostics::Edit;
+use ruff_python_ast::PySourceType;
+use ruff_text_size::TextRange;
+use tower_lsp::lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, CodeActionResponse,
+    Range as LspRange, TextEdit, Url, WorkspaceEdit,
+};
+
+use crate::diagnostics::lint;
+use crate::line_index::{LineIndex, PositionEncoding};
+
+/// `source.fixAll.ruff`: apply every safe fix in one go.
+pub(crate) const FIX_ALL_KIND: &str = "source.fixAll.ruff";
+/// `source.organizeImports.ruff`: sort/deduplicate imports only.
+pub(crate) const ORGANIZE_IMPORTS_KIND: &str = "source.organizeImports.ruff";
+
+/// Builds the code actions for `params`, given the document's current `source`.
+pub(crate) fn code_actions(
+    uri: &Url,
+    path: &Path,
+    source: &str,
+    source_type: PySourceType,
+    line_index: &LineIndex,
+    encoding: PositionEncoding,
+    params: &CodeActionParams,
+) -> CodeActionResponse {
+    let mut actions = CodeActionResponse::new();
+
+    actions.extend(quick_fixes(
+        uri,
+        path,
+        source,
+        source_type,
+        line_index,
+        encoding,
+        params.range,
+    ));
+
+    // `CodeActionKind`s are hierarchical: a client asking for the generic `source.fixAll`
+    // (as most editors' "fix on save" settings do) should also match our more specific
+    // `source.fixAll.ruff`.
+    let wants = |kind: &str| {
+        params.context.only.as_ref().is_none_or(|only| {
+            only.iter()
+                .any(|k| k.as_str() == kind || kind.starts_with(&format!("{}.", k.as_str())))
+        })
+    };
+
+    if wants(FIX_ALL_KIND) {
+        if let Some(action) = fix_all_action(uri, path, source, source_type, line_index, encoding)
+        {
+            actions.push(CodeActionOrCommand::CodeAction(action));
+        }
+    }
+    if wants(ORGANIZE_IMPORTS_KIND) {
+        if let Some(action) =
+            organize_imports_action(uri, path, source, source_type, line_index, encoding)
+        {
+            actions.push(CodeActionOrCommand::CodeAction(action));
+        }
+    }
+
+    actions
+}
+
+/// One `quickfix` action per diagnostic overlapping `range` that carries a fix.
+fn quick_fixes(
+    uri: &Url,
+    path: &Path,
+    source: &str,
+    source_type: PySourceType,
+    line_index: &LineIndex,
+    encoding: PositionEncoding,
+    range: LspRange,
+) -> Vec<CodeActionOrCommand> {
+    lint(path, source, source_type)
+        .into_iter()
+        .filter_map(|diagnostic| {
+            let fix = diagnostic.fix.as_ref()?;
+            let diagnostic_range = line_index.to_range(source, diagnostic.range(), encoding);
+            if !ranges_overlap(diagnostic_range, range) {
+                return None;
+            }
+
+            let edits = edits_for(source, line_index, encoding, fix.edits());
+            Some(CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("Fix {}", diagnostic.kind.rule().noqa_code()),
+                kind: Some(CodeActionKind::QUICKFIX),
+                edit: Some(workspace_edit(uri, edits)),
+                is_preferred: Some(true),
+                ..CodeAction::default()
+            }))
+        })
+        .collect()
+}
+
+fn fix_all_action(
+    uri: &Url,
+    path: &Path,
+    source: &str,
+    source_type: PySourceType,
+    line_index: &LineIndex,
+    encoding: PositionEncoding,
+) -> Option<CodeAction> {
+    action_for_fixed_source(
+        uri,
+        path,
+        source,
+        source_type,
+        line_index,
+        encoding,
+        "Fix all auto-fixable problems",
+        FIX_ALL_KIND,
+        None,
+    )
+}
+
+fn organize_imports_action(
+    uri: &Url,
+    path: &Path,
+    source: &str,
+    source_type: PySourceType,
+    line_index: &LineIndex,
+    encoding: PositionEncoding,
+) -> Option<CodeAction> {
+    action_for_fixed_source(
+        uri,
+        path,
+        source,
+        source_type,
+        line_index,
+        encoding,
+        "Organize imports",
+        ORGANIZE_IMPORTS_KIND,
+        Some(isort_only_settings(&Settings::default())),
+    )
+}
+
+fn action_for_fixed_source(
+    uri: &Url,
+    path: &Path,
+    source: &str,
+    source_type: PySourceType,
+    line_index: &LineIndex,
+    encoding: PositionEncoding,
+    title: &str,
+    kind: &str,
+    settings: Option<Settings>,
+) -> Option<CodeAction> {
+    let settings = settings.unwrap_or_default();
+
+    let result = lint_fix(path, source, Noqa::Enabled, &settings, source_type).ok()?;
+    if result.fixed_contents == source {
+        return None;
+    }
+
+    let edits = diff_to_edits(source, &result.fixed_contents, line_index, encoding);
+    Some(CodeAction {
+        title: title.to_string(),
+        kind: Some(CodeActionKind::new(kind)),
+        edit: Some(workspace_edit(uri, edits)),
+        ..CodeAction::default()
+    })
+}
+
+/// Restricts `settings` to the import-sorting rule only, for `organizeImports`.
+fn isort_only_settings(settings: &Settings) -> Settings {
+    let mut settings = settings.clone();
+    settings.linter.rules.clear();
+    settings
+        .linter
+        .rules
+        .enable(ruff::registry::Rule::UnsortedImports);
+    settings
+}
+
+/// Converts a [`Fix`](ruff_diagnostics::Fix)'s edits into non-overlapping LSP [`TextEdit`]s.
+fn edits_for(
+    source: &str,
+    line_index: &LineIndex,
+    encoding: PositionEncoding,
+    edits: impl Iterator<Item = Edit>,
+) -> Vec<TextEdit> {
+    edits
+        .map(|edit| TextEdit {
+            range: line_index.to_range(source, edit.range(), encoding),
+            new_text: edit.content().unwrap_or_default().to_string(),
+        })
+        .collect()
+}
+
+/// Reduces `original` -> `fixed` to a single edit over their differing middle, by stripping
+/// the common prefix and suffix. One diagnostic's fixes never overlap another's, so a single
+/// edit per code action is both correct and the minimal diff a client needs to apply.
+///
+/// Walks `char_indices()` rather than bytes, so the trimmed offsets always land on a char
+/// boundary -- two differing multi-byte characters can share a leading byte (`é` = `C3 A9`
+/// vs. `è` = `C3 A8`), and a byte-for-byte comparison would stop partway through one.
+fn diff_to_edits(
+    original: &str,
+    fixed: &str,
+    line_index: &LineIndex,
+    encoding: PositionEncoding,
+) -> Vec<TextEdit> {
+    let prefix_len = original
+        .char_indices()
+        .zip(fixed.char_indices())
+        .take_while(|((_, a), (_, b))| a == b)
+        .last()
+        .map_or(0, |((i, c), _)| i + c.len_utf8());
+    let suffix_len = original[prefix_len..]
+        .chars()
+        .rev()
+        .zip(fixed[prefix_len..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(c, _)| c.len_utf8())
+        .sum();
+
+    let original_end = original.len() - suffix_len;
+    let fixed_end = fixed.len() - suffix_len;
+
+    vec![TextEdit {
+        range: line_index.to_range(
+            original,
+            TextRange::new((prefix_len as u32).into(), (original_end as u32).into()),
+            encoding,
+        ),
+        new_text: fixed[prefix_len..fixed_end].to_string(),
+    }]
+}
+
+fn workspace_edit(uri: &Url, edits: Vec<TextEdit>) -> WorkspaceEdit {
+    WorkspaceEdit {
+        changes: Some([(uri.clone(), edits)].into_iter().collect()),
+        ..WorkspaceEdit::default()
+    }
+}
+
+fn ranges_overlap(a: LspRange, b: LspRange) -> bool {
+    a.start <= b.end && b.start <= a.end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(original: &str, fixed: &str) -> TextEdit {
+        let line_index = LineIndex::new(original);
+        let mut edits = diff_to_edits(original, fixed, &line_index, PositionEncoding::Utf8);
+        assert_eq!(edits.len(), 1);
+        edits.remove(0)
+    }
+
+    #[test]
+    fn differing_multi_byte_chars_sharing_a_leading_byte_does_not_panic() {
+        // `é` (U+00E9, `C3 A9`) and `è` (U+00E8, `C3 A8`) share a leading byte, so a
+        // byte-for-byte trim used to land mid-character here and panic.
+        let edit = edit("café", "cafè");
+        assert_eq!(edit.new_text, "è");
+        assert_eq!(edit.range.start.character, 3);
+        assert_eq!(edit.range.end.character, 5);
+    }
+
+    #[test]
+    fn pure_insertion() {
+        let edit = edit("foo", "foobar");
+        assert_eq!(edit.new_text, "bar");
+        assert_eq!(edit.range.start.character, 3);
+        assert_eq!(edit.range.end.character, 3);
+    }
+
+    #[test]
+    fn pure_deletion() {
+        let edit = edit("foobar", "foo");
+        assert_eq!(edit.new_text, "");
+        assert_eq!(edit.range.start.character, 3);
+        assert_eq!(edit.range.end.character, 6);
+    }
+}
@@ -0,0 +1,63 @@
+use std::path::Path;
+
+use ruff::linter::lint_only;
+use ruff::settings::{flags::Noqa, Settings};
+use ruff_diagnostics::Diagnostic;
+use ruff_python_ast::PySourceType;
+use tower_lsp::lsp_types::{Diagnostic as LspDiagnostic, DiagnosticSeverity, NumberOrString};
+
+use crate::line_index::{LineIndex, PositionEncoding};
+
+/// Runs Ruff's check pipeline over `source`, returning the raw diagnostics.
+///
+/// Used both to publish LSP diagnostics and, by the code-action handler, to find the
+/// [`Fix`](ruff_diagnostics::Fix) backing a given quick fix.
+pub(crate) fn lint(path: &Path, source: &str, source_type: PySourceType) -> Vec<Diagnostic> {
+    let settings = Settings::default();
+    lint_only(source, path, None, &settings, Noqa::Enabled, source_type).data
+}
+
+/// Runs Ruff's check pipeline over `source` and converts the results into LSP diagnostics.
+pub(crate) fn diagnostics_for_source(
+    path: &Path,
+    source: &str,
+    source_type: PySourceType,
+    line_index: &LineIndex,
+    encoding: PositionEncoding,
+) -> Vec<LspDiagnostic> {
+    lint(path, source, source_type)
+        .iter()
+        .map(|diagnostic| to_lsp_diagnostic(source, line_index, encoding, diagnostic))
+        .collect()
+}
+
+fn to_lsp_diagnostic(
+    source: &str,
+    line_index: &LineIndex,
+    encoding: PositionEncoding,
+    diagnostic: &Diagnostic,
+) -> LspDiagnostic {
+    let rule = diagnostic.kind.rule();
+    let code = rule.noqa_code().to_string();
+
+    LspDiagnostic {
+        range: line_index.to_range(source, diagnostic.range(), encoding),
+        severity: Some(severity_for_code(&code)),
+        code: Some(NumberOrString::String(code)),
+        code_description: None,
+        source: Some("ruff".to_string()),
+        message: diagnostic.kind.body.clone(),
+        related_information: None,
+        tags: None,
+        data: None,
+    }
+}
+
+/// E999 is Ruff's syntax-error diagnostic; everything else is an advisory lint.
+fn severity_for_code(code: &str) -> DiagnosticSeverity {
+    if code == "E999" {
+        DiagnosticSeverity::ERROR
+    } else {
+        DiagnosticSeverity::WARNING
+    }
+}
@@ -1,15 +1,31 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+
 use ruff::RUFF_PKG_VERSION;
 use std::future::Future;
 use std::pin::Pin;
 use tower_lsp::jsonrpc::Result as LspResult;
 use tower_lsp::lsp_types::{
-    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
-    DocumentFormattingParams, InitializeParams, InitializeResult, InitializedParams, MessageType,
-    PositionEncodingKind, ServerCapabilities, ServerInfo, TextDocumentSyncCapability,
-    TextDocumentSyncKind, TextEdit,
+    CodeActionKind, CodeActionOptions, CodeActionParams, CodeActionProviderCapability,
+    CodeActionResponse, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentFormattingParams, DocumentHighlight,
+    DocumentHighlightParams, InitializeParams, InitializeResult, InitializedParams, Location,
+    MessageType, OneOf, PositionEncodingKind, ReferenceParams, RenameParams, ServerCapabilities,
+    ServerInfo, TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url, WorkspaceEdit,
 };
 use tower_lsp::{Client, LanguageServer, LspService};
 
+mod code_action;
+mod diagnostics;
+mod line_index;
+mod references;
+mod session;
+
+use crate::code_action::{code_actions, FIX_ALL_KIND, ORGANIZE_IMPORTS_KIND};
+use crate::diagnostics::diagnostics_for_source;
+use crate::line_index::PositionEncoding;
+use crate::session::{path_for, Session};
+
 /// Creates a LSP server that reads from stdin and writes the output to stdout.
 pub fn stdio() {
     let mut rt = tokio::runtime::Runtime::new().unwrap();
@@ -17,7 +33,11 @@ pub fn stdio() {
         let stdin = tokio::io::stdin();
         let stdout = tokio::io::stdout();
 
-        let (service, socket) = LspService::new(|client| Server { client });
+        let (service, socket) = LspService::new(|client| Server {
+            client,
+            session: Session::default(),
+            encoding: Mutex::new(PositionEncoding::Utf16),
+        });
         tower_lsp::Server::new(stdin, stdout, socket)
             .serve(service)
             .await;
@@ -26,19 +46,62 @@ pub fn stdio() {
 
 struct Server {
     client: Client,
+    /// Every currently-open document, keyed by its URL.
+    session: Session,
+    /// The position encoding negotiated with the client during `initialize`.
+    encoding: Mutex<PositionEncoding>,
+}
+
+impl Server {
+    /// Lints the stored text for `uri` and publishes the resulting diagnostics.
+    async fn publish_diagnostics_for(&self, uri: Url) {
+        let encoding = *self.encoding.lock().unwrap();
+        let path = path_for(&uri);
+        let Some((diagnostics, version)) = self.session.with_document(&uri, |document| {
+            let diagnostics = diagnostics_for_source(
+                &path,
+                document.text(),
+                document.source_type(),
+                document.line_index(),
+                encoding,
+            );
+            (diagnostics, document.version())
+        }) else {
+            return;
+        };
+        self.client
+            .publish_diagnostics(uri, diagnostics, Some(version))
+            .await;
+    }
 }
 
 #[tower_lsp::async_trait]
 impl LanguageServer for Server {
     #[tracing::instrument(level="debug", skip_all, err, fields(client=?params.client_info))]
     async fn initialize(&self, params: InitializeParams) -> LspResult<InitializeResult> {
+        let encoding = PositionEncoding::negotiate(params.capabilities.general.as_ref());
+        *self.encoding.lock().unwrap() = encoding;
+
         let init = InitializeResult {
             capabilities: ServerCapabilities {
-                // TODO
-                position_encoding: None,
+                position_encoding: Some(encoding.kind()),
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(
                     TextDocumentSyncKind::INCREMENTAL,
                 )),
+                code_action_provider: Some(CodeActionProviderCapability::Options(
+                    CodeActionOptions {
+                        code_action_kinds: Some(vec![
+                            CodeActionKind::QUICKFIX,
+                            CodeActionKind::new(FIX_ALL_KIND),
+                            CodeActionKind::new(ORGANIZE_IMPORTS_KIND),
+                        ]),
+                        work_done_progress_options: Default::default(),
+                        resolve_provider: None,
+                    },
+                )),
+                references_provider: Some(OneOf::Left(true)),
+                document_highlight_provider: Some(OneOf::Left(true)),
+                rename_provider: Some(OneOf::Left(true)),
                 ..Default::default()
             },
             server_info: Some(ServerInfo {
@@ -54,13 +117,125 @@ impl LanguageServer for Server {
     async fn initialized(&self, params: InitializedParams) {}
 
     #[tracing::instrument(skip_all, fields(file=%params.text_document.uri))]
-    async fn did_open(&self, params: DidOpenTextDocumentParams) {}
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let uri = params.text_document.uri;
+        self.session.open(
+            uri.clone(),
+            params.text_document.text,
+            params.text_document.version,
+        );
+        self.publish_diagnostics_for(uri).await;
+    }
 
     #[tracing::instrument(skip_all, fields(file=%params.text_document.uri))]
-    async fn did_change(&self, params: DidChangeTextDocumentParams) {}
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        let uri = params.text_document.uri;
+        let encoding = *self.encoding.lock().unwrap();
+        self.session.change(
+            &uri,
+            params.text_document.version,
+            params.content_changes,
+            encoding,
+        );
+        self.publish_diagnostics_for(uri).await;
+    }
 
     #[tracing::instrument(skip_all, fields(file=%params.text_document.uri))]
-    async fn did_close(&self, params: DidCloseTextDocumentParams) {}
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        let version = self.session.with_document(&uri, |document| document.version());
+        self.session.close(&uri);
+        self.client
+            .publish_diagnostics(uri, Vec::new(), version)
+            .await;
+    }
+
+    #[tracing::instrument(skip_all, fields(file=%params.text_document.uri))]
+    async fn code_action(
+        &self,
+        params: CodeActionParams,
+    ) -> LspResult<Option<CodeActionResponse>> {
+        let uri = params.text_document.uri.clone();
+        let encoding = *self.encoding.lock().unwrap();
+        let path = path_for(&uri);
+        Ok(self.session.with_document(&uri, |document| {
+            code_actions(
+                &uri,
+                &path,
+                document.text(),
+                document.source_type(),
+                document.line_index(),
+                encoding,
+                &params,
+            )
+        }))
+    }
+
+    #[tracing::instrument(skip_all, fields(file=%params.text_document_position.text_document.uri))]
+    async fn references(&self, params: ReferenceParams) -> LspResult<Option<Vec<Location>>> {
+        let uri = params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+        let include_declaration = params.context.include_declaration;
+        let encoding = *self.encoding.lock().unwrap();
+        let path = path_for(&uri);
+        Ok(Some(
+            self.session
+                .with_document(&uri, |document| {
+                    let offset = document.line_index().to_offset(document.text(), position, encoding);
+                    references::find_references(
+                        &uri,
+                        &path,
+                        document.text(),
+                        document.line_index(),
+                        encoding,
+                        offset,
+                        include_declaration,
+                    )
+                })
+                .unwrap_or_default(),
+        ))
+    }
+
+    #[tracing::instrument(skip_all, fields(file=%params.text_document_position_params.text_document.uri))]
+    async fn document_highlight(
+        &self,
+        params: DocumentHighlightParams,
+    ) -> LspResult<Option<Vec<DocumentHighlight>>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+        let encoding = *self.encoding.lock().unwrap();
+        let path = path_for(&uri);
+        Ok(self.session.with_document(&uri, |document| {
+            let offset = document.line_index().to_offset(document.text(), position, encoding);
+            references::document_highlights(&path, document.text(), document.line_index(), encoding, offset)
+        }))
+    }
+
+    #[tracing::instrument(skip_all, fields(file=%params.text_document_position.text_document.uri))]
+    async fn rename(&self, params: RenameParams) -> LspResult<Option<WorkspaceEdit>> {
+        let uri = params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+        let encoding = *self.encoding.lock().unwrap();
+        let path = path_for(&uri);
+        let Some(result) = self.session.with_document(&uri, |document| {
+            let offset = document.line_index().to_offset(document.text(), position, encoding);
+            references::rename(
+                &uri,
+                &path,
+                document.text(),
+                document.line_index(),
+                encoding,
+                offset,
+                &params.new_name,
+            )
+        }) else {
+            return Ok(None);
+        };
+
+        result
+            .map(Some)
+            .map_err(|message| tower_lsp::jsonrpc::Error::invalid_params(message))
+    }
 
     #[tracing::instrument(skip_all, fields(file=%params.text_document.uri))]
     async fn formatting(
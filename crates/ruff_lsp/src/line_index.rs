@@ -0,0 +1,179 @@
+use ruff_text_size::{TextRange, TextSize};
+use tower_lsp::lsp_types::{GeneralClientCapabilities, Position, PositionEncodingKind, Range};
+
+/// The character encoding the client and server have agreed to exchange positions in.
+///
+/// Ruff itself only ever works in byte offsets (see `ruff_text_size`); this is purely about
+/// how those offsets are expressed on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PositionEncoding {
+    Utf8,
+    Utf16,
+}
+
+impl PositionEncoding {
+    /// Picks UTF-8 if the client offers it, falling back to UTF-16 (the LSP default) otherwise.
+    pub(crate) fn negotiate(general: Option<&GeneralClientCapabilities>) -> Self {
+        let offers_utf8 = general
+            .and_then(|general| general.position_encodings.as_deref())
+            .is_some_and(|encodings| encodings.contains(&PositionEncodingKind::UTF8));
+        if offers_utf8 {
+            Self::Utf8
+        } else {
+            Self::Utf16
+        }
+    }
+
+    pub(crate) fn kind(self) -> PositionEncodingKind {
+        match self {
+            Self::Utf8 => PositionEncodingKind::UTF8,
+            Self::Utf16 => PositionEncodingKind::UTF16,
+        }
+    }
+}
+
+/// The byte offset of the start of every line in a document, enabling `O(log n)` conversion
+/// between byte offsets and LSP `(line, character)` positions.
+#[derive(Debug, Clone)]
+pub(crate) struct LineIndex {
+    line_starts: Vec<TextSize>,
+}
+
+impl LineIndex {
+    pub(crate) fn new(text: &str) -> Self {
+        let mut line_starts = vec![TextSize::from(0)];
+        line_starts.extend(
+            text.match_indices('\n')
+                .map(|(i, _)| TextSize::try_from(i + 1).unwrap()),
+        );
+        Self { line_starts }
+    }
+
+    /// The line containing `offset`, and that line's own start offset.
+    fn line_at(&self, offset: TextSize) -> (usize, TextSize) {
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        (line, self.line_starts[line])
+    }
+
+    /// Converts a byte `offset` into `text` to an LSP [`Position`] in `encoding`.
+    pub(crate) fn to_position(&self, text: &str, offset: TextSize, encoding: PositionEncoding) -> Position {
+        let offset = offset.min(TextSize::of(text));
+        let (line, line_start) = self.line_at(offset);
+        let mut line_text = &text[line_start.into()..offset.into()];
+        // The offset may land between a line's `\r` and `\n`; the `\r` belongs to no position.
+        if let Some(stripped) = line_text.strip_suffix('\r') {
+            line_text = stripped;
+        }
+        let character = match encoding {
+            PositionEncoding::Utf8 => line_text.len() as u32,
+            // A character above U+FFFF is encoded as a two-unit UTF-16 surrogate pair, so it
+            // counts for 2 rather than the 1 unit every other scalar value counts for.
+            PositionEncoding::Utf16 => line_text.encode_utf16().count() as u32,
+        };
+        Position::new(line as u32, character)
+    }
+
+    /// Converts an LSP [`Position`] in `encoding` back to a byte offset into `text`.
+    pub(crate) fn to_offset(&self, text: &str, position: Position, encoding: PositionEncoding) -> TextSize {
+        let Some(&line_start) = self.line_starts.get(position.line as usize) else {
+            return TextSize::of(text);
+        };
+        let line_end = self
+            .line_starts
+            .get(position.line as usize + 1)
+            .copied()
+            .unwrap_or_else(|| TextSize::of(text));
+        let line_text = &text[line_start.into()..line_end.into()];
+
+        let column = match encoding {
+            PositionEncoding::Utf8 => TextSize::try_from(position.character).unwrap_or(TextSize::of(line_text)),
+            PositionEncoding::Utf16 => {
+                let mut remaining_units = position.character;
+                let mut column = TextSize::from(0);
+                for ch in line_text.chars() {
+                    if remaining_units == 0 {
+                        break;
+                    }
+                    let units = ch.len_utf16() as u32;
+                    if remaining_units < units {
+                        break;
+                    }
+                    remaining_units -= units;
+                    column += TextSize::try_from(ch.len_utf8()).unwrap();
+                }
+                column
+            }
+        };
+        line_start + column.min(TextSize::of(line_text))
+    }
+
+    /// Converts a [`TextRange`] into `text` to an LSP [`Range`] in `encoding`.
+    pub(crate) fn to_range(&self, text: &str, range: TextRange, encoding: PositionEncoding) -> Range {
+        Range::new(
+            self.to_position(text, range.start(), encoding),
+            self.to_position(text, range.end(), encoding),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utf16_offset_mid_line() {
+        let text = "abc def";
+        let index = LineIndex::new(text);
+        let offset = index.to_offset(text, Position::new(0, 4), PositionEncoding::Utf16);
+        assert_eq!(&text[usize::from(offset)..], "def");
+    }
+
+    #[test]
+    fn utf8_and_utf16_round_trip() {
+        let text = "abc def";
+        let index = LineIndex::new(text);
+        let offset = TextSize::from(4);
+        for encoding in [PositionEncoding::Utf8, PositionEncoding::Utf16] {
+            let position = index.to_position(text, offset, encoding);
+            assert_eq!(index.to_offset(text, position, encoding), offset);
+        }
+    }
+
+    #[test]
+    fn surrogate_pair_counts_as_two_utf16_units() {
+        // U+1F600 (an emoji) lies above U+FFFF, so it's encoded as a UTF-16 surrogate pair.
+        let text = "a\u{1F600}b";
+        let index = LineIndex::new(text);
+
+        let before_emoji = index.to_position(text, TextSize::from(1), PositionEncoding::Utf16);
+        assert_eq!(before_emoji, Position::new(0, 1));
+
+        let after_emoji = index.to_position(text, TextSize::from(5), PositionEncoding::Utf16);
+        assert_eq!(after_emoji, Position::new(0, 3));
+
+        let offset = index.to_offset(text, Position::new(0, 3), PositionEncoding::Utf16);
+        assert_eq!(offset, TextSize::from(5));
+    }
+
+    #[test]
+    fn crlf_offset_excludes_carriage_return() {
+        let text = "foo\r\nbar";
+        let index = LineIndex::new(text);
+
+        // The offset between "\r" and "\n" should report the same position as the offset
+        // right before "\r" -- the `\r` itself belongs to no column.
+        let between_cr_lf = index.to_position(text, TextSize::from(4), PositionEncoding::Utf8);
+        assert_eq!(between_cr_lf, Position::new(0, 3));
+
+        let second_line_start = index.to_offset(text, Position::new(1, 0), PositionEncoding::Utf8);
+        assert_eq!(&text[usize::from(second_line_start)..], "bar");
+    }
+
+    #[test]
+    fn offset_past_end_of_document_clamps() {
+        let text = "abc";
+        let index = LineIndex::new(text);
+        let position = index.to_position(text, TextSize::from(100), PositionEncoding::Utf8);
+        assert_eq!(position, Position::new(0, 3));
+    }
+}
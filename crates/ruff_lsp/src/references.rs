@@ -0,0 +1,132 @@
+use std::path::Path;
+
+use ruff::linter::check_with_semantic_model;
+use ruff::settings::{flags::Noqa, Settings};
+use ruff_python_ast::{PySourceType, Ranged};
+use ruff_python_semantic::{Binding, SemanticModel};
+use ruff_text_size::{TextRange, TextSize};
+use tower_lsp::lsp_types::{DocumentHighlight, Location, TextEdit, Url, WorkspaceEdit};
+
+use crate::line_index::{LineIndex, PositionEncoding};
+
+fn parse(path: &Path, source: &str) -> SemanticModel<'_> {
+    let settings = Settings::default();
+    let source_type = PySourceType::from(path);
+    check_with_semantic_model(source, path, &settings, Noqa::Enabled, source_type).semantic
+}
+
+/// Walks every binding in `semantic` to find the one that owns `offset`, either because the
+/// offset falls inside the binding's own defining range, or inside the range of one of the
+/// reads that resolved to it. There's no reverse index from reference to binding, so this is
+/// a linear scan -- acceptable at the scale of a single open document.
+fn binding_at<'a>(semantic: &'a SemanticModel, offset: TextSize) -> Option<&'a Binding<'a>> {
+    semantic.all_bindings().find_map(|(_, binding)| {
+        let owns_offset = binding.range().contains(offset)
+            || binding
+                .references()
+                .any(|reference_id| semantic.reference(reference_id).range().contains(offset));
+        owns_offset.then_some(binding)
+    })
+}
+
+/// The binding's own defining range, plus every resolved reference to it.
+fn ranges_for(semantic: &SemanticModel, binding: &Binding) -> Vec<TextRange> {
+    let mut ranges: Vec<TextRange> = binding
+        .references()
+        .map(|reference_id| semantic.reference(reference_id).range())
+        .collect();
+    ranges.push(binding.range());
+    ranges
+}
+
+/// `textDocument/references` / `documentHighlight` share the same underlying set: the
+/// binding at `offset` plus every place it's read. `references` drops the definition itself
+/// from that set unless the client asks for it via `include_declaration`.
+fn ranges_at(source: &str, path: &Path, offset: TextSize, include_declaration: bool) -> Vec<TextRange> {
+    let semantic = parse(path, source);
+    let Some(binding) = binding_at(&semantic, offset) else {
+        return Vec::new();
+    };
+
+    let mut ranges = ranges_for(&semantic, binding);
+    if !include_declaration {
+        ranges.retain(|&range| range != binding.range());
+    }
+    ranges
+}
+
+pub(crate) fn find_references(
+    uri: &Url,
+    path: &Path,
+    source: &str,
+    line_index: &LineIndex,
+    encoding: PositionEncoding,
+    offset: TextSize,
+    include_declaration: bool,
+) -> Vec<Location> {
+    ranges_at(source, path, offset, include_declaration)
+        .into_iter()
+        .map(|range| Location::new(uri.clone(), line_index.to_range(source, range, encoding)))
+        .collect()
+}
+
+pub(crate) fn document_highlights(
+    path: &Path,
+    source: &str,
+    line_index: &LineIndex,
+    encoding: PositionEncoding,
+    offset: TextSize,
+) -> Vec<DocumentHighlight> {
+    ranges_at(source, path, offset, true)
+        .into_iter()
+        .map(|range| DocumentHighlight {
+            range: line_index.to_range(source, range, encoding),
+            kind: None,
+        })
+        .collect()
+}
+
+/// `textDocument/rename`: replaces the binding's own range and every resolved reference to
+/// it. Refuses when the cursor lands on an [`UnresolvedReference`](ruff_python_semantic::UnresolvedReference)
+/// -- including one that might be satisfied by a wildcard import -- since there's no binding
+/// to safely rewrite.
+pub(crate) fn rename(
+    uri: &Url,
+    path: &Path,
+    source: &str,
+    line_index: &LineIndex,
+    encoding: PositionEncoding,
+    offset: TextSize,
+    new_name: &str,
+) -> Result<WorkspaceEdit, String> {
+    let semantic = parse(path, source);
+
+    if let Some(unresolved) = semantic
+        .unresolved_references()
+        .iter()
+        .find(|reference| reference.range().contains(offset))
+    {
+        return Err(if unresolved.is_wildcard_import() {
+            "cannot rename a name that may be bound by a wildcard import".to_string()
+        } else {
+            "cannot rename an unresolved reference".to_string()
+        });
+    }
+
+    let Some(binding) = binding_at(&semantic, offset) else {
+        return Err("no renameable symbol at this position".to_string());
+    };
+
+    let edits = ranges_for(&semantic, binding)
+        .into_iter()
+        .map(|range| TextEdit {
+            range: line_index.to_range(source, range, encoding),
+            new_text: new_name.to_string(),
+        })
+        .collect();
+
+    Ok(WorkspaceEdit {
+        changes: Some([(uri.clone(), edits)].into_iter().collect()),
+        ..WorkspaceEdit::default()
+    })
+}
@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+
+use dashmap::DashMap;
+use ruff_python_ast::PySourceType;
+use tower_lsp::lsp_types::{TextDocumentContentChangeEvent, Url};
+
+use crate::line_index::{LineIndex, PositionEncoding};
+
+/// An open text document and everything the other handlers need to know about it.
+pub(crate) struct Document {
+    text: String,
+    version: i32,
+    source_type: PySourceType,
+    line_index: LineIndex,
+}
+
+impl Document {
+    fn new(uri: &Url, text: String, version: i32) -> Self {
+        let source_type = PySourceType::from(path_for(uri).as_path());
+        let line_index = LineIndex::new(&text);
+        Self {
+            text,
+            version,
+            source_type,
+            line_index,
+        }
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub(crate) fn source_type(&self) -> PySourceType {
+        self.source_type
+    }
+
+    pub(crate) fn line_index(&self) -> &LineIndex {
+        &self.line_index
+    }
+
+    pub(crate) fn version(&self) -> i32 {
+        self.version
+    }
+
+    /// Applies a single content-change event, then refreshes the cached line index.
+    fn apply_change(&mut self, change: TextDocumentContentChangeEvent, encoding: PositionEncoding) {
+        match change.range {
+            Some(range) => {
+                let start = self.line_index.to_offset(&self.text, range.start, encoding);
+                let end = self.line_index.to_offset(&self.text, range.end, encoding);
+                self.text
+                    .replace_range(usize::from(start)..usize::from(end), &change.text);
+            }
+            None => self.text = change.text,
+        }
+        self.line_index = LineIndex::new(&self.text);
+    }
+}
+
+pub(crate) fn path_for(uri: &Url) -> PathBuf {
+    uri.to_file_path().unwrap_or_else(|()| PathBuf::from(uri.path()))
+}
+
+/// The set of documents the client currently has open, keyed by URL.
+#[derive(Default)]
+pub(crate) struct Session {
+    documents: DashMap<Url, Document>,
+}
+
+impl Session {
+    pub(crate) fn open(&self, uri: Url, text: String, version: i32) {
+        self.documents.insert(uri.clone(), Document::new(&uri, text, version));
+    }
+
+    /// Applies `changes` to `uri`'s document, in order, and bumps it to `version`.
+    pub(crate) fn change(
+        &self,
+        uri: &Url,
+        version: i32,
+        changes: Vec<TextDocumentContentChangeEvent>,
+        encoding: PositionEncoding,
+    ) {
+        if let Some(mut document) = self.documents.get_mut(uri) {
+            for change in changes {
+                document.apply_change(change, encoding);
+            }
+            document.version = version;
+        }
+    }
+
+    pub(crate) fn close(&self, uri: &Url) {
+        self.documents.remove(uri);
+    }
+
+    /// Runs `f` with a reference to `uri`'s document, if it's open.
+    pub(crate) fn with_document<T>(&self, uri: &Url, f: impl FnOnce(&Document) -> T) -> Option<T> {
+        self.documents.get(uri).map(|document| f(&document))
+    }
+}